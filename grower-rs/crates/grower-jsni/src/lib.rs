@@ -1,4 +1,6 @@
 use wasm_bindgen::prelude::wasm_bindgen;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::vec;
 
 #[allow(unused_imports)]
@@ -20,6 +22,9 @@ pub enum JSNIKind {
     Char,
     String,
     VecU8,
+    Array,
+    ExternRef,
+    Error,
     Null,
 }
 
@@ -29,6 +34,82 @@ pub struct JSNIValue {
     pub value: u64,
 }
 
+/// How a tracked allocation must be reclaimed at teardown.
+#[derive(Debug, Clone, Copy)]
+enum AllocKind {
+    /// A `Vec<u8>`'s buffer, leaked via `mem::forget` and keyed by its data pointer.
+    BytesRaw,
+    /// A `Vec<JSNIValue>`'s buffer, leaked via `mem::forget` and keyed by its data pointer.
+    JsniValuesRaw,
+    /// A `Vec<u8>` leaked whole via `Box::into_raw`, keyed by the box pointer.
+    BytesBoxed,
+    /// A `Vec<JSNIValue>` leaked whole via `Box::into_raw`, keyed by the box pointer.
+    JsniValuesBoxed,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AllocRecord {
+    len: usize,
+    kind: AllocKind,
+}
+
+fn alloc_registry() -> &'static Mutex<HashMap<usize, AllocRecord>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<usize, AllocRecord>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn register_alloc(ptr: usize, len: usize, kind: AllocKind) {
+    alloc_registry()
+        .lock()
+        .unwrap()
+        .insert(ptr, AllocRecord { len, kind });
+}
+
+fn deregister_alloc(ptr: usize) {
+    alloc_registry().lock().unwrap().remove(&ptr);
+}
+
+/// Number of allocations still outstanding in the registry (debug accounting).
+#[wasm_bindgen]
+pub fn jsni_outstanding_allocations() -> usize {
+    alloc_registry().lock().unwrap().len()
+}
+
+/// Sum of the tracked lengths of allocations still outstanding (debug accounting).
+#[wasm_bindgen]
+pub fn jsni_outstanding_len() -> usize {
+    alloc_registry().lock().unwrap().values().map(|r| r.len).sum()
+}
+
+/// Frees every allocation still tracked by the registry. Call this once, when the host
+/// is discarding the WASM instance, to reclaim memory from values that never round-tripped
+/// through `call`/`free_args` (e.g. because an error path dropped them early).
+///
+/// This does not reclaim JS-side externref handle table slots: `JSNIValue` is `Copy` and has
+/// no `Drop`, so an `ExternRef` that never passes through `free_args` or `drop_externref`
+/// leaks its slot permanently. Callers that hand out `ExternRef` values must make sure every
+/// one is eventually freed on its own.
+#[wasm_bindgen]
+pub fn jsni_teardown() {
+    let mut registry = alloc_registry().lock().unwrap();
+    for (ptr, record) in registry.drain() {
+        match record.kind {
+            AllocKind::BytesRaw => unsafe {
+                Vec::from_raw_parts(ptr as *mut u8, record.len, record.len);
+            },
+            AllocKind::JsniValuesRaw => unsafe {
+                Vec::from_raw_parts(ptr as *mut JSNIValue, record.len, record.len);
+            },
+            AllocKind::BytesBoxed => unsafe {
+                drop(Box::from_raw(ptr as *mut Vec<u8>));
+            },
+            AllocKind::JsniValuesBoxed => unsafe {
+                drop(Box::from_raw(ptr as *mut Vec<JSNIValue>));
+            },
+        };
+    }
+}
+
 macro_rules! impl_from_primitive {
     ($ty:ty, $kind:expr) => {
         impl From<$ty> for JSNIValue {
@@ -88,6 +169,7 @@ impl From<Vec<u8>> for JSNIValue {
         let len = value.len();
         let ptr = value.as_ptr() as *mut u8;
         std::mem::forget(value); // Prevent Rust from freeing the memory
+        register_alloc(ptr as usize, len, AllocKind::BytesRaw);
         JSNIValue {
             kind: JSNIKind::VecU8,
             // high: len 32bit, low: ptr 64bit
@@ -102,6 +184,7 @@ impl From<String> for JSNIValue {
         let bytes = value.into_bytes();
         let ptr = bytes.as_ptr() as *mut u8;
         std::mem::forget(bytes); // Prevent Rust from freeing the memory
+        register_alloc(ptr as usize, len, AllocKind::BytesRaw);
         JSNIValue {
             kind: JSNIKind::String,
             value: (len as u64) << 32 | ptr as u64,
@@ -109,6 +192,167 @@ impl From<String> for JSNIValue {
     }
 }
 
+impl From<Vec<JSNIValue>> for JSNIValue {
+    fn from(value: Vec<JSNIValue>) -> Self {
+        let len = value.len();
+        let ptr = value.as_ptr() as *mut u8;
+        std::mem::forget(value); // Prevent Rust from freeing the memory
+        register_alloc(ptr as usize, len, AllocKind::JsniValuesRaw);
+        JSNIValue {
+            kind: JSNIKind::Array,
+            // high: len 32bit, low: ptr 64bit
+            value: (len as u64) << 32 | ptr as u64,
+        }
+    }
+}
+
+/// Error surfaced when decoding a `JSNIValue` (or an argument array) fails, instead of
+/// the accessor panicking and aborting the whole WASM instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JSNIError {
+    WrongKind {
+        expected: JSNIKind,
+        found: JSNIKind,
+    },
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+    InvalidUtf8,
+    InvalidValue {
+        kind: JSNIKind,
+    },
+}
+
+impl std::fmt::Display for JSNIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JSNIError::WrongKind { expected, found } => {
+                write!(f, "expected JSNIValue of kind {:?}, found {:?}", expected, found)
+            }
+            JSNIError::IndexOutOfBounds { index, len } => {
+                write!(f, "argument index {} out of bounds (len {})", index, len)
+            }
+            JSNIError::InvalidUtf8 => write!(f, "JSNIValue bytes are not valid UTF-8"),
+            JSNIError::InvalidValue { kind } => {
+                write!(f, "JSNIValue of kind {:?} carries an invalid value", kind)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JSNIError {}
+
+/// Bounds-checked indexed access into a `JSNIValue` argument array, following neon's
+/// `try_get` pattern so a wrong argument count surfaces as a `JSNIError` instead of a panic.
+/// Functions registered with `register_jsni_fn` use this to decode their arguments.
+pub trait JSNIArgs {
+    fn try_get(&self, index: usize) -> Result<&JSNIValue, JSNIError>;
+}
+
+impl JSNIArgs for [JSNIValue] {
+    fn try_get(&self, index: usize) -> Result<&JSNIValue, JSNIError> {
+        self.get(index).ok_or(JSNIError::IndexOutOfBounds {
+            index,
+            len: self.len(),
+        })
+    }
+}
+
+macro_rules! impl_tryfrom_primitive {
+    ($ty:ty, $kind:expr) => {
+        impl TryFrom<JSNIValue> for $ty {
+            type Error = JSNIError;
+            fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+                if value.kind != $kind {
+                    return Err(JSNIError::WrongKind {
+                        expected: $kind,
+                        found: value.kind,
+                    });
+                }
+                Ok(value.value as $ty)
+            }
+        }
+    };
+}
+
+impl_tryfrom_primitive!(i8, JSNIKind::I8);
+impl_tryfrom_primitive!(i16, JSNIKind::I16);
+impl_tryfrom_primitive!(i32, JSNIKind::I32);
+impl_tryfrom_primitive!(i64, JSNIKind::I64);
+impl_tryfrom_primitive!(u8, JSNIKind::U8);
+impl_tryfrom_primitive!(u16, JSNIKind::U16);
+impl_tryfrom_primitive!(u32, JSNIKind::U32);
+impl_tryfrom_primitive!(u64, JSNIKind::U64);
+
+impl TryFrom<JSNIValue> for bool {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        if value.kind != JSNIKind::Bool {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::Bool,
+                found: value.kind,
+            });
+        }
+        Ok(value.value != 0)
+    }
+}
+
+impl TryFrom<JSNIValue> for char {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        if value.kind != JSNIKind::Char {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::Char,
+                found: value.kind,
+            });
+        }
+        char::from_u32(value.value as u32).ok_or(JSNIError::InvalidValue {
+            kind: JSNIKind::Char,
+        })
+    }
+}
+
+impl TryFrom<JSNIValue> for f32 {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        if value.kind != JSNIKind::F32 {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::F32,
+                found: value.kind,
+            });
+        }
+        Ok(f32::from_le_bytes((value.value as u32).to_le_bytes()))
+    }
+}
+
+impl TryFrom<JSNIValue> for f64 {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        if value.kind != JSNIKind::F64 {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::F64,
+                found: value.kind,
+            });
+        }
+        Ok(f64::from_le_bytes(value.value.to_le_bytes()))
+    }
+}
+
+impl TryFrom<JSNIValue> for String {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        value.try_to_string()
+    }
+}
+
+impl TryFrom<JSNIValue> for Vec<u8> {
+    type Error = JSNIError;
+    fn try_from(value: JSNIValue) -> Result<Self, Self::Error> {
+        value.try_to_vec()
+    }
+}
+
 impl JSNIValue {
     pub fn null() -> Self {
         JSNIValue {
@@ -117,30 +361,164 @@ impl JSNIValue {
         }
     }
 
-    pub fn to_vec(&self) ->Vec<u8> {
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.try_to_vec().expect("JSNIValue is not a Vec<u8>")
+    }
+
+    pub fn try_to_vec(&self) -> Result<Vec<u8>, JSNIError> {
         if self.kind != JSNIKind::VecU8 {
-            panic!("JSNIValue is not a Vec<u8>");
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::VecU8,
+                found: self.kind,
+            });
         }
         let ptr = self.value & 0xFFFFFFFF;
-        unsafe { *Box::from_raw(ptr as *mut Vec<u8>) }
+        deregister_alloc(ptr as usize);
+        Ok(unsafe { *Box::from_raw(ptr as *mut Vec<u8>) })
     }
 
     pub fn to_string(&self) -> String {
+        self.try_to_string().expect("JSNIValue is not a String")
+    }
+
+    pub fn try_to_string(&self) -> Result<String, JSNIError> {
         if self.kind != JSNIKind::String {
-            panic!("JSNIValue is not a String");
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::String,
+                found: self.kind,
+            });
         }
         let ptr = self.value & 0xFFFFFFFF;
+        deregister_alloc(ptr as usize);
         let vec = unsafe { *Box::from_raw(ptr as *mut Vec<u8>) };
-        String::from_utf8(vec).unwrap()
+        String::from_utf8(vec).map_err(|_| JSNIError::InvalidUtf8)
+    }
+
+    pub fn to_array(&self) -> Vec<JSNIValue> {
+        self.try_to_array().expect("JSNIValue is not an Array")
+    }
+
+    pub fn try_to_array(&self) -> Result<Vec<JSNIValue>, JSNIError> {
+        if self.kind != JSNIKind::Array {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::Array,
+                found: self.kind,
+            });
+        }
+        let len = (self.value >> 32) as usize;
+        let ptr = (self.value & 0xFFFFFFFF) as *mut JSNIValue;
+        deregister_alloc(ptr as usize);
+        Ok(unsafe { Vec::from_raw_parts(ptr, len, len) })
+    }
+
+    /// Wraps a slot index into the JS-side externref handle table.
+    pub fn externref(slot: u32) -> Self {
+        JSNIValue {
+            kind: JSNIKind::ExternRef,
+            value: slot as u64,
+        }
+    }
+
+    pub fn to_externref(&self) -> u32 {
+        self.try_to_externref().expect("JSNIValue is not an ExternRef")
+    }
+
+    pub fn try_to_externref(&self) -> Result<u32, JSNIError> {
+        if self.kind != JSNIKind::ExternRef {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::ExternRef,
+                found: self.kind,
+            });
+        }
+        Ok(self.value as u32)
+    }
+
+    pub fn to_error(&self) -> String {
+        self.try_to_error().expect("JSNIValue is not an Error")
+    }
+
+    pub fn try_to_error(&self) -> Result<String, JSNIError> {
+        if self.kind != JSNIKind::Error {
+            return Err(JSNIError::WrongKind {
+                expected: JSNIKind::Error,
+                found: self.kind,
+            });
+        }
+        let ptr = self.value & 0xFFFFFFFF;
+        deregister_alloc(ptr as usize);
+        let vec = unsafe { *Box::from_raw(ptr as *mut Vec<u8>) };
+        String::from_utf8(vec).map_err(|_| JSNIError::InvalidUtf8)
+    }
+
+    /// Wraps a captured JS exception message as an `Error` JSNIValue.
+    pub fn error(message: String) -> Self {
+        let len = message.len();
+        let bytes = message.into_bytes();
+        let ptr = bytes.as_ptr() as *mut u8;
+        std::mem::forget(bytes); // Prevent Rust from freeing the memory
+        register_alloc(ptr as usize, len, AllocKind::BytesRaw);
+        JSNIValue {
+            kind: JSNIKind::Error,
+            value: (len as u64) << 32 | ptr as u64,
+        }
     }
 }
 
+/// Slot reserved for JS `undefined` in the externref handle table.
+pub const EXTERNREF_UNDEFINED: u32 = 0;
+/// Slot reserved for JS `null` in the externref handle table.
+pub const EXTERNREF_NULL: u32 = 1;
+
 pub struct JavaScriptNativeInterface {
 }
 
 #[wasm_bindgen]
 extern "C" {
-    async fn jsni_call(js_func_name: *const u8, args: *const u8, args_count: usize) -> JsValue;
+    /// Calls a JS function. `error_out` is a pointer to a `JSNIValue` slot that the JS glue
+    /// fills in with an `Error` value when the call throws; callers must only read it when
+    /// the return value is `JSNI_CALL_THREW`.
+    async fn jsni_call(
+        js_func_name: *const u8,
+        args: *const u8,
+        args_count: usize,
+        error_out: *mut JSNIValue,
+    ) -> JsValue;
+
+    /// Duplicates the handle-table entry at `slot`, returning the index of the new slot.
+    fn clone_externref(slot: u32) -> u32;
+
+    /// Releases the handle-table entry at `slot`, returning it to the free-list.
+    fn free_externref(slot: u32);
+}
+
+/// Sentinel returned by `jsni_call` when no values were returned.
+const JSNI_CALL_EMPTY: f64 = -1.0;
+/// Sentinel returned by `jsni_call` when the JS function threw; `error_out` holds the error.
+const JSNI_CALL_THREW: f64 = -2.0;
+
+/// A JS exception captured across the JSNI boundary.
+#[derive(Debug, Clone)]
+pub struct JsError {
+    pub message: String,
+}
+
+impl std::fmt::Display for JsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for JsError {}
+
+impl From<JSNIValue> for JsError {
+    fn from(value: JSNIValue) -> Self {
+        match value.try_to_error() {
+            Ok(message) => JsError { message },
+            Err(err) => JsError {
+                message: format!("<malformed JS error value: {}>", err),
+            },
+        }
+    }
 }
 
 fn vec_onto_box<T>(vec: Vec<T>) -> *mut Vec<T> {
@@ -153,6 +531,7 @@ pub fn alloc_jsni_value(size: usize) -> u64 {
     let mut vec = vec![JSNIValue::null(); size];
     let ptr = vec.as_mut_ptr() as *mut u8;
     let vec_ptr = vec_onto_box(vec);
+    register_alloc(vec_ptr as usize, size, AllocKind::JsniValuesBoxed);
     (vec_ptr as u64) << 32 | ptr as u64
 }
 
@@ -162,6 +541,7 @@ pub fn alloc(size: usize) -> u64 {
     let mut vec = vec![0u8; size];
     let ptr = vec.as_mut_ptr() as *mut u8;
     let vec_ptr = vec_onto_box(vec);
+    register_alloc(vec_ptr as usize, size, AllocKind::BytesBoxed);
     (vec_ptr as u64) << 32 | ptr as u64
 }
 
@@ -176,35 +556,242 @@ impl JavaScriptNativeInterface {
                 JSNIKind::VecU8 => {
                     let len = (arg.value >> 32) as usize;
                     let ptr = (arg.value & 0xFFFFFFFF) as *mut u8;
+                    deregister_alloc(ptr as usize);
                     unsafe { Vec::from_raw_parts(ptr, len, len) };
                 }
-                JSNIKind::String => {
+                JSNIKind::String | JSNIKind::Error => {
                     let len = (arg.value >> 32) as usize;
                     let ptr = (arg.value & 0xFFFFFFFF) as *mut u8;
+                    deregister_alloc(ptr as usize);
                     unsafe { String::from_raw_parts(ptr, len, len) };
                 }
+                JSNIKind::Array => {
+                    let len = (arg.value >> 32) as usize;
+                    let ptr = (arg.value & 0xFFFFFFFF) as *mut JSNIValue;
+                    deregister_alloc(ptr as usize);
+                    let elements = unsafe { Vec::from_raw_parts(ptr, len, len) };
+                    self.free_args(elements);
+                }
+                JSNIKind::ExternRef => {
+                    // `arg.kind` is already known to be `ExternRef` here, so read the slot
+                    // straight out of `value` rather than going through the fallible accessor.
+                    self.drop_externref(arg.value as u32);
+                }
                 _ => {}
             }
         }
     }
 
+    /// Duplicates a retained JS object handle, returning a new `ExternRef` JSNIValue
+    /// that must be separately freed (or passed on and freed by the callee).
+    pub fn clone_externref(&self, externref: JSNIValue) -> Result<JSNIValue, JSNIError> {
+        let slot = clone_externref(externref.try_to_externref()?);
+        Ok(JSNIValue::externref(slot))
+    }
+
+    /// Releases a retained JS object handle back to the handle table's free-list.
+    pub fn drop_externref(&self, slot: u32) {
+        if slot == EXTERNREF_UNDEFINED || slot == EXTERNREF_NULL {
+            return;
+        }
+        free_externref(slot);
+    }
+
     /// Calls the JavaScript function.
     /// Must be set registers with uarguments to pass to the JavaScript function before calling this function.
-    /// Returns a vector of results.
+    /// Returns a vector of results, or the `JsError` captured if the call threw.
     /// The first register is the count of results, followed by the results themselves.
-    pub async fn call(&mut self, js_func_name: String, args: Vec<JSNIValue>) -> Vec<JSNIValue> {
+    pub async fn call(
+        &mut self,
+        js_func_name: String,
+        args: Vec<JSNIValue>,
+    ) -> Result<Vec<JSNIValue>, JsError> {
         let js_func_name = JSNIValue::from(js_func_name);
         let js_func_name_ptr = &js_func_name as *const JSNIValue as *const u8;
+        let mut error_out = JSNIValue::null();
 
-        let return_values_ptr_raw = jsni_call(js_func_name_ptr, args.as_ptr() as *mut u8, args.len()).await.as_f64().unwrap();
+        let return_values_ptr_raw = jsni_call(
+            js_func_name_ptr,
+            args.as_ptr() as *mut u8,
+            args.len(),
+            &mut error_out as *mut JSNIValue,
+        )
+        .await
+        .as_f64()
+        .unwrap();
         self.free_args(args);
 
-        if return_values_ptr_raw < 0.0 {
-            // none returned
-            return Vec::new();
+        if return_values_ptr_raw == JSNI_CALL_THREW {
+            return Err(JsError::from(error_out));
+        }
+
+        if return_values_ptr_raw == JSNI_CALL_EMPTY {
+            return Ok(Vec::new());
+        }
+
+        // The JS glue builds this results buffer via `alloc_jsni_value`, which registers it
+        // under its box pointer; deregister it here so `jsni_teardown` doesn't later double-free
+        // memory this call already reclaimed.
+        let return_values_ptr = return_values_ptr_raw as u64 as usize;
+        deregister_alloc(return_values_ptr);
+        let return_values = unsafe { Box::from_raw(return_values_ptr as *mut Vec<JSNIValue>) };
+        Ok(*return_values)
+    }
+}
+
+/// A Rust function callable from JavaScript through `jsni_dispatch`. Receives its arguments
+/// as a borrowed slice so it can decode them with `JSNIArgs::try_get` instead of indexing.
+type JsniDispatchFn = dyn Fn(&[JSNIValue]) -> Vec<JSNIValue> + Send + Sync;
+
+/// Sentinel returned by `jsni_dispatch` when no function is registered under `name`.
+pub const JSNI_DISPATCH_NOT_FOUND: u64 = u64::MAX;
+/// Sentinel returned by `jsni_dispatch` when the registered closure panicked.
+pub const JSNI_DISPATCH_PANICKED: u64 = u64::MAX - 1;
+/// Sentinel returned by `jsni_dispatch` when `name_ptr` does not decode to a valid `String`.
+pub const JSNI_DISPATCH_INVALID_NAME: u64 = u64::MAX - 2;
+
+fn jsni_fn_registry() -> &'static Mutex<HashMap<String, Arc<JsniDispatchFn>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<JsniDispatchFn>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Registers `f` so that JavaScript can invoke it by `name` via `jsni_dispatch`. `f` receives
+/// its arguments as a slice, typically decoded with `JSNIArgs::try_get` followed by `try_into`.
+pub fn register_jsni_fn(
+    name: &str,
+    f: impl Fn(&[JSNIValue]) -> Vec<JSNIValue> + Send + Sync + 'static,
+) {
+    jsni_fn_registry()
+        .lock()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(f));
+}
+
+/// Entry point JavaScript calls to invoke a Rust function registered with `register_jsni_fn`.
+/// `name_ptr` points to a `JSNIValue` encoding the function name. `args_box_ptr` is the box
+/// pointer returned by `alloc_jsni_value` for the `Vec<JSNIValue>` of arguments; it is consumed
+/// (and deregistered from the allocation registry) the same way `call` consumes its results box.
+/// Returns a boxed `Vec<JSNIValue>` pointer holding the results, `JSNI_DISPATCH_NOT_FOUND`
+/// if no function is registered under `name`, `JSNI_DISPATCH_INVALID_NAME` if `name_ptr`
+/// does not decode to a valid `String`, or `JSNI_DISPATCH_PANICKED` if it panicked.
+#[wasm_bindgen]
+pub fn jsni_dispatch(name_ptr: *const u8, args_box_ptr: *const u8) -> u64 {
+    deregister_alloc(args_box_ptr as usize);
+    let args = unsafe { *Box::from_raw(args_box_ptr as *mut Vec<JSNIValue>) };
+    let name = match unsafe { (*(name_ptr as *const JSNIValue)).try_to_string() } {
+        Ok(name) => name,
+        Err(_) => return JSNI_DISPATCH_INVALID_NAME,
+    };
+
+    // Clone the Arc and drop the registry lock before invoking `f`: a dispatched function
+    // is free to trigger more JS interaction that calls back into `jsni_dispatch`, and
+    // holding the lock across that reentrant call would deadlock on the non-reentrant Mutex.
+    let f = {
+        let registry = jsni_fn_registry().lock().unwrap();
+        match registry.get(name.as_str()) {
+            Some(f) => Arc::clone(f),
+            None => return JSNI_DISPATCH_NOT_FOUND,
+        }
+    };
+
+    // A panicking callback must not unwind across the FFI boundary, so it is caught and
+    // reported as a sentinel instead.
+    let results = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&args))) {
+        Ok(results) => results,
+        Err(_) => return JSNI_DISPATCH_PANICKED,
+    };
+    vec_onto_box(results) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes the `(box_ptr << 32 | data_ptr)` fat pointer returned by `alloc_jsni_value`/`alloc`.
+    fn split_fat_ptr(packed: u64) -> (usize, usize) {
+        ((packed >> 32) as usize, (packed & 0xFFFFFFFF) as usize)
+    }
+
+    /// Builds a `String` JSNIValue the way `try_to_string` decodes it: the low 32 bits hold
+    /// the box pointer of a `Vec<u8>` allocated with `alloc`.
+    fn boxed_string_value(s: &str) -> JSNIValue {
+        let (box_ptr, data_ptr) = split_fat_ptr(alloc(s.len()));
+        unsafe { std::ptr::copy_nonoverlapping(s.as_ptr(), data_ptr as *mut u8, s.len()) };
+        JSNIValue {
+            kind: JSNIKind::String,
+            value: (s.len() as u64) << 32 | box_ptr as u64,
+        }
+    }
+
+    /// The allocation registry is process-wide, so tests that count or tear down its
+    /// entries run one at a time.
+    static REGISTRY_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock_registry_for_test() -> std::sync::MutexGuard<'static, ()> {
+        REGISTRY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[test]
+    fn alloc_jsni_value_roundtrip_does_not_leak_or_double_free() {
+        let _guard = lock_registry_for_test();
+        let (box_ptr, _data_ptr) = split_fat_ptr(alloc_jsni_value(4));
+        assert_eq!(jsni_outstanding_allocations(), 1);
+
+        // Mirrors how `call` consumes a results box: deregister, then reclaim via `Box::from_raw`.
+        deregister_alloc(box_ptr);
+        drop(unsafe { Box::from_raw(box_ptr as *mut Vec<JSNIValue>) });
+
+        // `jsni_teardown` must not see (and therefore not double-free) the already-consumed box.
+        jsni_teardown();
+        assert_eq!(jsni_outstanding_allocations(), 0);
+    }
+
+    #[test]
+    fn jsni_teardown_reclaims_unconsumed_allocations() {
+        let _guard = lock_registry_for_test();
+        alloc_jsni_value(4);
+        alloc(8);
+        assert_eq!(jsni_outstanding_allocations(), 2);
+
+        jsni_teardown();
+        assert_eq!(jsni_outstanding_allocations(), 0);
+    }
+
+    #[test]
+    fn jsni_dispatch_consumes_its_args_box_without_leaking() {
+        let _guard = lock_registry_for_test();
+        register_jsni_fn("echo_count", |args| vec![JSNIValue::from(args.len() as u32)]);
+
+        let name = boxed_string_value("echo_count");
+        let name_ptr = &name as *const JSNIValue as *const u8;
+        let (args_box_ptr, data_ptr) = split_fat_ptr(alloc_jsni_value(2));
+        unsafe {
+            *(data_ptr as *mut JSNIValue) = JSNIValue::from(1i32);
+            *((data_ptr as *mut JSNIValue).add(1)) = JSNIValue::from(2i32);
         }
 
-        let return_values = unsafe { Box::from_raw(return_values_ptr_raw as u64 as *mut Vec<JSNIValue>) };
-        *return_values
+        let results_ptr = jsni_dispatch(name_ptr, args_box_ptr as *const u8);
+        assert_ne!(results_ptr, JSNI_DISPATCH_NOT_FOUND);
+        assert_ne!(results_ptr, JSNI_DISPATCH_PANICKED);
+        assert_ne!(results_ptr, JSNI_DISPATCH_INVALID_NAME);
+
+        let results = *unsafe { Box::from_raw(results_ptr as *mut Vec<JSNIValue>) };
+        assert_eq!(u32::try_from(results[0]), Ok(2));
+
+        // The args box was deregistered by `jsni_dispatch` itself, so nothing should remain.
+        assert_eq!(jsni_outstanding_allocations(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn jsni_dispatch_reports_unknown_function_names() {
+        let _guard = lock_registry_for_test();
+        let name = boxed_string_value("does_not_exist");
+        let name_ptr = &name as *const JSNIValue as *const u8;
+        let (args_box_ptr, _) = split_fat_ptr(alloc_jsni_value(0));
+
+        assert_eq!(
+            jsni_dispatch(name_ptr, args_box_ptr as *const u8),
+            JSNI_DISPATCH_NOT_FOUND
+        );
+    }
+}